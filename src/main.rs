@@ -1,12 +1,15 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use gif::{ColorOutput, DecodeOptions, DisposalMethod};
 use image::{
-    codecs::gif::GifDecoder, imageops, AnimationDecoder, DynamicImage, GenericImageView,
-    ImageFormat, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+    imageops, Delay, DynamicImage, Frame, GenericImageView, ImageFormat, Rgba, RgbaImage,
 };
 use imageproc::filter::gaussian_blur_f32;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::Duration; // Import blur function
 
@@ -30,13 +33,47 @@ struct Args {
     #[arg(long, default_value_t = 1.0)]
     contrast: f32,
 
-    /// Loop GIF animation indefinitely
+    /// Force infinite looping, overriding the GIF's embedded NETSCAPE loop count
     #[arg(long)]
     loop_gif: bool,
 
     /// Output ASCII art with ANSI colors
     #[arg(long)]
     color: bool,
+
+    /// ANSI color depth to emit when --color is set
+    #[arg(long, value_enum, default_value_t = ColorMode::Truecolor)]
+    color_mode: ColorMode,
+
+    /// Stabilize pixel values across GIF frames to reduce character flicker
+    #[arg(long)]
+    denoise: bool,
+
+    /// Render the ASCII art back into a raster image (PNG for stills, GIF for animations)
+    /// instead of printing it to the terminal
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Width in pixels of each rendered character cell
+    #[arg(long, default_value_t = 8)]
+    glyph_width: u32,
+
+    /// Height in pixels of each rendered character cell
+    #[arg(long, default_value_t = 16)]
+    glyph_height: u32,
+}
+
+/// ANSI color depth supported for `--color` output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ColorMode {
+    /// 24-bit `\x1B[38;2;r;g;bm` foreground color codes
+    Truecolor,
+    /// xterm 256-color palette, `\x1B[38;5;{idx}m`
+    #[value(name = "256")]
+    Palette256,
+    /// Basic 16-color ANSI palette, `\x1B[3{0-7}m` / `\x1B[9{0-7}m`
+    #[value(name = "16")]
+    Palette16,
 }
 
 // Character sets
@@ -49,44 +86,200 @@ const ASCII_LEN_SIMPLE: usize = ASCII_CHARS_SIMPLE.len();
 const ASPECT_RATIO_CORRECTION: f64 = 0.55;
 const MIN_FRAME_DELAY_MS: u64 = 20; // Minimum delay for GIF frames (ms)
 
+// --- 256-color xterm palette ---
+// Indices 16..=231 form a 6x6x6 color cube; indices 232..=255 are a 24-step
+// grayscale ramp. Both tables are the standard xterm levels.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn nearest_cube_level(value: u8) -> (u8, u8) {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &level)| (level as i32 - value as i32).abs())
+        .map(|(i, &level)| (i as u8, level))
+        .unwrap()
+}
+
+// Euclidean (squared) distance between two RGB colors.
+fn color_dist_sq(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    dr * dr + dg * dg + db * db
+}
+
+fn nearest_256_index(r: u8, g: u8, b: u8) -> u8 {
+    let (r_i, r_level) = nearest_cube_level(r);
+    let (g_i, g_level) = nearest_cube_level(g);
+    let (b_i, b_level) = nearest_cube_level(b);
+    let cube_index = 16 + 36 * r_i + 6 * g_i + b_i;
+    let cube_dist = color_dist_sq((r, g, b), (r_level, g_level, b_level));
+
+    // 24-step grayscale ramp: 8, 18, 28, ..., 238. Round to the nearest step
+    // rather than flooring, so e.g. luminance 17 picks level 18, not 8.
+    let gray_value = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+    let diff = (gray_value as i32 - 8).max(0);
+    let gray_step = ((diff + 5) / 10).min(23) as u8;
+    let gray_level = 8 + gray_step * 10;
+    let gray_index = 232 + gray_step;
+    let gray_dist = color_dist_sq((r, g, b), (gray_level, gray_level, gray_level));
+
+    if gray_dist < cube_dist {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+// Standard 16-color ANSI palette, approximated by the RGB values terminals
+// conventionally render them as. Pairs are (ansi code, (r, g, b)).
+const ANSI_16_PALETTE: [(u8, (u8, u8, u8)); 16] = [
+    (30, (0, 0, 0)),
+    (31, (128, 0, 0)),
+    (32, (0, 128, 0)),
+    (33, (128, 128, 0)),
+    (34, (0, 0, 128)),
+    (35, (128, 0, 128)),
+    (36, (0, 128, 128)),
+    (37, (192, 192, 192)),
+    (90, (128, 128, 128)),
+    (91, (255, 0, 0)),
+    (92, (0, 255, 0)),
+    (93, (255, 255, 0)),
+    (94, (0, 0, 255)),
+    (95, (255, 0, 255)),
+    (96, (0, 255, 255)),
+    (97, (255, 255, 255)),
+];
+
+fn nearest_16_code(r: u8, g: u8, b: u8) -> u8 {
+    ANSI_16_PALETTE
+        .iter()
+        .min_by_key(|(_, color)| color_dist_sq((r, g, b), *color))
+        .map(|(code, _)| *code)
+        .unwrap()
+}
+
+// Inverse of `nearest_16_code`: approximate RGB for a `3{0-7}`/`9{0-7}` code.
+fn rgb_from_16_code(code: u8) -> (u8, u8, u8) {
+    ANSI_16_PALETTE
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, color)| *color)
+        .unwrap_or((255, 255, 255))
+}
+
+// Inverse of `nearest_256_index`: approximate RGB for an xterm-256 index, so
+// `render_ascii_frame` can recover real colors from `--color-mode 256` output.
+fn rgb_from_256_index(idx: u8) -> (u8, u8, u8) {
+    if idx < 16 {
+        // Indices 0..=15 are the standard 16-color palette.
+        ANSI_16_PALETTE[idx as usize].1
+    } else if idx <= 231 {
+        let i = idx - 16;
+        let r = CUBE_LEVELS[(i / 36) as usize];
+        let g = CUBE_LEVELS[((i % 36) / 6) as usize];
+        let b = CUBE_LEVELS[(i % 6) as usize];
+        (r, g, b)
+    } else {
+        let level = 8 + (idx - 232) as u32 * 10;
+        (level as u8, level as u8, level as u8)
+    }
+}
+
+// Recover the approximate RGB color a `render_ascii_frame`-consumed ANSI
+// escape sequence represents, from its semicolon-split parts (without the
+// leading `\x1B[` or trailing `m`). Handles all three `--color-mode`
+// encodings `ansi_color_code` can emit, plus `SGR 0` / unrecognized codes
+// resetting to white.
+fn color_from_ansi_parts(parts: &[&str]) -> Rgba<u8> {
+    match (parts.first(), parts.get(1), parts.get(2)) {
+        (Some(&"38"), Some(&"2"), _) => {
+            if let (Some(r), Some(g), Some(b)) = (parts.get(2), parts.get(3), parts.get(4)) {
+                Rgba([
+                    r.parse().unwrap_or(255),
+                    g.parse().unwrap_or(255),
+                    b.parse().unwrap_or(255),
+                    255,
+                ])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        }
+        (Some(&"38"), Some(&"5"), _) => {
+            if let Some(Ok(idx)) = parts.get(2).map(|s| s.parse::<u8>()) {
+                let (r, g, b) = rgb_from_256_index(idx);
+                Rgba([r, g, b, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        }
+        (Some(code), _, _) => match code.parse::<u8>() {
+            Ok(code @ (30..=37 | 90..=97)) => {
+                let (r, g, b) = rgb_from_16_code(code);
+                Rgba([r, g, b, 255])
+            }
+            _ => Rgba([255, 255, 255, 255]), // reset ("0") or anything else
+        },
+        (None, _, _) => Rgba([255, 255, 255, 255]),
+    }
+}
+
+// Build the ANSI foreground color escape sequence for a pixel in the
+// requested color mode.
+fn ansi_color_code(r: u8, g: u8, b: u8, color_mode: ColorMode) -> String {
+    match color_mode {
+        ColorMode::Truecolor => format!("\x1B[38;2;{};{};{}m", r, g, b),
+        ColorMode::Palette256 => format!("\x1B[38;5;{}m", nearest_256_index(r, g, b)),
+        ColorMode::Palette16 => format!("\x1B[{}m", nearest_16_code(r, g, b)),
+    }
+}
+
 // Function to convert a single image frame to ASCII art with color
-fn image_to_ascii(
-    img: &DynamicImage,
-    width: u32,
-    invert: bool,
-    contrast: f32,
-    use_color: bool,
-) -> Result<String, Box<dyn std::error::Error>> {
-    // --- Resize ---
+// Resize an input frame and apply the color/grayscale preprocessing
+// (blur for color mode, desaturation for grayscale) that used to live inline
+// in `image_to_ascii`. Pulled out so callers that need identical dimensions
+// across many frames (e.g. GIF denoising) can do this once up front instead
+// of redoing it inside the per-frame loop.
+fn prepare_frame(img: &DynamicImage, width: u32, use_color: bool) -> DynamicImage {
     let new_height = (img.height() as f64 * width as f64 * ASPECT_RATIO_CORRECTION
         / img.width() as f64)
         .max(1.0) as u32; // Ensure height is at least 1
     let resized_img = img.resize_exact(width, new_height, imageops::FilterType::Lanczos3);
 
-    // --- Map to ASCII ---
-    let mut ascii_art = String::new();
-    let reset_code = "\x1B[0m";
-
-    // --- Select character set & Apply optional blur for color mode ---
-    let ascii_chars = ASCII_CHARS_SIMPLE;
-    let ascii_len = ASCII_LEN_SIMPLE;
-    let final_img_buffer; // Need owned buffer for processing
-
     if use_color {
         let rgba_img = resized_img.to_rgba8();
         // Apply slight blur only for color mode to suppress noise
-        final_img_buffer = DynamicImage::ImageRgba8(gaussian_blur_f32(&rgba_img, 0.6));
-        ascii_art.reserve((width * new_height * 20 + new_height) as usize);
+        DynamicImage::ImageRgba8(gaussian_blur_f32(&rgba_img, 0.6))
     } else {
         // No blur for grayscale
-        final_img_buffer = DynamicImage::ImageLuma8(resized_img.grayscale().to_luma8());
-        ascii_art.reserve((width * new_height + new_height) as usize);
+        DynamicImage::ImageLuma8(resized_img.grayscale().to_luma8())
     }
+}
+
+// Map an already-prepared (resized + blurred/grayscaled) frame to an ASCII
+// art string.
+fn ascii_from_prepared(
+    final_img_buffer: &DynamicImage,
+    invert: bool,
+    contrast: f32,
+    use_color: bool,
+    color_mode: ColorMode,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut ascii_art = String::new();
+    let reset_code = "\x1B[0m";
+    let ascii_chars = ASCII_CHARS_SIMPLE;
+    let ascii_len = ASCII_LEN_SIMPLE;
 
-    // We need to handle color vs grayscale pixel access differently now
     let img_width = final_img_buffer.width();
     let img_height = final_img_buffer.height();
 
+    if use_color {
+        ascii_art.reserve((img_width * img_height * 20 + img_height) as usize);
+    } else {
+        ascii_art.reserve((img_width * img_height + img_height) as usize);
+    }
+
     for y in 0..img_height {
         for x in 0..img_width {
             let (r, g, b, luminance); // Declare vars for color/luminance
@@ -98,7 +291,7 @@ fn image_to_ascii(
                 b = pixel[2];
                 luminance = (0.2126 * r as f32) + (0.7152 * g as f32) + (0.0722 * b as f32);
                 // Generate and push color code directly
-                ascii_art.push_str(&format!("\x1B[38;2;{};{};{}m", r, g, b));
+                ascii_art.push_str(&ansi_color_code(r, g, b, color_mode));
             } else {
                 let pixel = final_img_buffer.get_pixel(x, y); // Grayscale (Luma)
                 luminance = pixel[0] as f32; // Luminance is just the grayscale value
@@ -136,6 +329,540 @@ fn image_to_ascii(
     Ok(ascii_art)
 }
 
+// Convert a single image frame to ASCII art. Thin wrapper around
+// `prepare_frame` + `ascii_from_prepared` for callers (the static image path
+// and the streaming GIF workers) that convert each frame independently and
+// don't need the two stages split.
+fn image_to_ascii(
+    img: &DynamicImage,
+    width: u32,
+    invert: bool,
+    contrast: f32,
+    use_color: bool,
+    color_mode: ColorMode,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let prepared = prepare_frame(img, width, use_color);
+    ascii_from_prepared(&prepared, invert, contrast, use_color, color_mode)
+}
+
+// --- Temporal denoising across GIF frames ---
+//
+// Tiny per-frame luminance/color noise can flip a pixel between adjacent
+// ASCII characters every frame, causing flicker. To stabilize this, each
+// pixel position keeps a running "reference" value; a new frame's pixel is
+// only accepted (and becomes the new reference) if it differs from the
+// reference by more than a small threshold, otherwise the reference value
+// is reused.
+const DENOISE_LUMA_THRESHOLD: i32 = 8;
+const DENOISE_CHANNEL_THRESHOLD: i32 = 8;
+
+fn denoise_frames(frames: &mut [DynamicImage], use_color: bool) {
+    if frames.is_empty() {
+        return;
+    }
+
+    if use_color {
+        let mut reference = frames[0].to_rgba8();
+        for frame in frames.iter_mut() {
+            let mut buf = frame.to_rgba8();
+            for (px, reference_px) in buf.pixels_mut().zip(reference.pixels_mut()) {
+                let dr = (px[0] as i32 - reference_px[0] as i32).abs();
+                let dg = (px[1] as i32 - reference_px[1] as i32).abs();
+                let db = (px[2] as i32 - reference_px[2] as i32).abs();
+                if dr <= DENOISE_CHANNEL_THRESHOLD
+                    && dg <= DENOISE_CHANNEL_THRESHOLD
+                    && db <= DENOISE_CHANNEL_THRESHOLD
+                {
+                    *px = *reference_px;
+                } else {
+                    *reference_px = *px;
+                }
+            }
+            *frame = DynamicImage::ImageRgba8(buf);
+        }
+    } else {
+        let mut reference = frames[0].to_luma8();
+        for frame in frames.iter_mut() {
+            let mut buf = frame.to_luma8();
+            for (px, reference_px) in buf.pixels_mut().zip(reference.pixels_mut()) {
+                let delta = (px[0] as i32 - reference_px[0] as i32).abs();
+                if delta <= DENOISE_LUMA_THRESHOLD {
+                    *px = *reference_px;
+                } else {
+                    *reference_px = *px;
+                }
+            }
+            *frame = DynamicImage::ImageLuma8(buf);
+        }
+    }
+}
+
+// --- Bitmap font for rendering ASCII art back into raster images ---
+//
+// Only `ASCII_CHARS_SIMPLE` is ever produced by `image_to_ascii`, so the font
+// only needs glyphs for those ten characters. Each glyph is a 5x7 bitmap,
+// stored one row per byte with the five pixel columns in the low bits
+// (bit 4 = leftmost column, bit 0 = rightmost column).
+const GLYPH_COLS: u32 = 5;
+const GLYPH_ROWS: u32 = 7;
+
+fn glyph_bitmap(c: char) -> [u8; GLYPH_ROWS as usize] {
+    match c {
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '*' => [0x00, 0x15, 0x0E, 0x1F, 0x0E, 0x15, 0x00],
+        '#' => [0x0A, 0x1F, 0x0A, 0x0A, 0x0A, 0x1F, 0x0A],
+        '%' => [0x19, 0x19, 0x02, 0x04, 0x08, 0x13, 0x13],
+        '@' => [0x0E, 0x11, 0x17, 0x15, 0x17, 0x01, 0x0E],
+        _ => [0x00; GLYPH_ROWS as usize], // space and anything unrecognized renders blank
+    }
+}
+
+// Parse one pre-rendered ASCII frame (as produced by `image_to_ascii`, ANSI
+// color codes and all) into an `RgbaImage`, drawing each character with the
+// bitmap font above and scaling glyphs up to the requested cell size.
+fn render_ascii_frame(
+    ascii: &str,
+    glyph_width: u32,
+    glyph_height: u32,
+) -> Result<RgbaImage, Box<dyn std::error::Error>> {
+    let lines: Vec<&str> = ascii.lines().collect();
+    let cols = lines.iter().map(|l| visible_len(l)).max().unwrap_or(0) as u32;
+    let rows = lines.len() as u32;
+
+    let mut canvas = RgbaImage::from_pixel(
+        (cols * glyph_width).max(1),
+        (rows * glyph_height).max(1),
+        Rgba([0, 0, 0, 255]),
+    );
+
+    for (row, line) in lines.iter().enumerate() {
+        let mut col: u32 = 0;
+        let mut current_color = Rgba([255, 255, 255, 255]);
+        let mut chars = line.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            if ch == '\x1B' {
+                // Consume a CSI escape sequence: ESC '[' ... 'm'
+                if chars.peek() == Some(&'[') {
+                    chars.next();
+                    let mut code = String::new();
+                    for c in chars.by_ref() {
+                        if c == 'm' {
+                            break;
+                        }
+                        code.push(c);
+                    }
+                    let parts: Vec<&str> = code.split(';').collect();
+                    current_color = color_from_ansi_parts(&parts);
+                }
+                continue;
+            }
+
+            draw_glyph(&mut canvas, ch, col * glyph_width, row as u32 * glyph_height, glyph_width, glyph_height, current_color);
+            col += 1;
+        }
+    }
+
+    Ok(canvas)
+}
+
+// Length of a line ignoring ANSI escape sequences, so column counts line up.
+fn visible_len(line: &str) -> usize {
+    let mut len = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1B' {
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c == 'm' {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        len += 1;
+    }
+    len
+}
+
+fn draw_glyph(
+    canvas: &mut RgbaImage,
+    c: char,
+    x0: u32,
+    y0: u32,
+    glyph_width: u32,
+    glyph_height: u32,
+    color: Rgba<u8>,
+) {
+    let bitmap = glyph_bitmap(c);
+    for py in 0..glyph_height {
+        let row = (py * GLYPH_ROWS / glyph_height.max(1)).min(GLYPH_ROWS - 1);
+        let bits = bitmap[row as usize];
+        for px in 0..glyph_width {
+            let col = (px * GLYPH_COLS / glyph_width.max(1)).min(GLYPH_COLS - 1);
+            let on = (bits >> (GLYPH_COLS - 1 - col)) & 1 == 1;
+            if on {
+                canvas.put_pixel(x0 + px, y0 + py, color);
+            }
+        }
+    }
+}
+
+// --- Faithful GIF playback: loop count, disposal, and transparency ---
+//
+// Decoded via the `gif` crate directly (rather than `image`'s
+// `AnimationDecoder`) because we need each raw sub-frame's left/top offset,
+// disposal method, and transparency, not just a cloned full-canvas buffer.
+fn open_gif_decoder(path: &PathBuf) -> Result<gif::Decoder<BufReader<File>>, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    Ok(options.read_info(reader)?)
+}
+
+// Resolve the GIF's embedded NETSCAPE 2.0 loop count into the `image`
+// crate's `Repeat` type, overridden by `--loop_gif` forcing infinite looping.
+fn resolve_repeat(force_infinite: bool, embedded: gif::Repeat) -> Repeat {
+    if force_infinite {
+        return Repeat::Infinite;
+    }
+    match embedded {
+        gif::Repeat::Infinite => Repeat::Infinite,
+        // A file that requests 0 repetitions still needs to display once.
+        gif::Repeat::Finite(n) => Repeat::Finite(n.max(1)),
+    }
+}
+
+// Iterator that reads raw (possibly partial, delta-encoded) GIF sub-frames
+// and composites them onto a persistent canvas according to each frame's
+// disposal method and transparent-index pixels, yielding a full-canvas RGBA
+// image per frame exactly as it would appear on screen.
+struct CompositedGifFrames {
+    decoder: gif::Decoder<BufReader<File>>,
+    canvas: RgbaImage,
+    // Disposal action owed to the canvas from the previous frame, applied
+    // before the next frame is drawn: (method, left, top, width, height).
+    pending_disposal: Option<(DisposalMethod, u32, u32, u32, u32)>,
+    // Canvas region saved before drawing a frame whose disposal is
+    // `Previous`, restored once that frame is done being displayed.
+    saved_region: Option<RgbaImage>,
+}
+
+impl CompositedGifFrames {
+    fn new(decoder: gif::Decoder<BufReader<File>>) -> Self {
+        let width = decoder.width() as u32;
+        let height = decoder.height() as u32;
+        Self {
+            decoder,
+            canvas: RgbaImage::from_pixel(width.max(1), height.max(1), Rgba([0, 0, 0, 0])),
+            pending_disposal: None,
+            saved_region: None,
+        }
+    }
+
+    fn repeat(&self) -> gif::Repeat {
+        self.decoder.repeat()
+    }
+}
+
+impl Iterator for CompositedGifFrames {
+    type Item = Result<(RgbaImage, Duration), Box<dyn std::error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = match self.decoder.read_next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e.into())),
+        };
+
+        let canvas_w = self.canvas.width();
+        let canvas_h = self.canvas.height();
+
+        // Apply the disposal owed by the *previous* frame before drawing
+        // this one onto the canvas. Sub-frame bounds come straight from the
+        // GIF's own frame header, so a malformed file can claim a region
+        // that spills past the logical screen; clamp against the canvas so
+        // compositing can't panic on out-of-bounds pixel access.
+        if let Some((method, left, top, w, h)) = self.pending_disposal.take() {
+            match method {
+                DisposalMethod::Background => {
+                    for y in top..(top + h).min(canvas_h) {
+                        for x in left..(left + w).min(canvas_w) {
+                            self.canvas.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+                        }
+                    }
+                }
+                DisposalMethod::Previous => {
+                    if let Some(saved) = self.saved_region.take() {
+                        for y in 0..h {
+                            if top + y >= canvas_h {
+                                continue;
+                            }
+                            for x in 0..w {
+                                if left + x >= canvas_w {
+                                    continue;
+                                }
+                                self.canvas.put_pixel(left + x, top + y, *saved.get_pixel(x, y));
+                            }
+                        }
+                    }
+                }
+                DisposalMethod::Any | DisposalMethod::Keep => {}
+            }
+        }
+
+        let left = frame.left as u32;
+        let top = frame.top as u32;
+        let w = frame.width as u32;
+        let h = frame.height as u32;
+
+        // If this frame wants to restore-to-previous afterwards, snapshot
+        // the canvas region it's about to overwrite. Pixels that fall
+        // outside the canvas (out-of-bounds sub-frame) are left transparent
+        // in the snapshot since there's no canvas content to save there.
+        if frame.dispose == DisposalMethod::Previous {
+            let mut region = RgbaImage::from_pixel(w, h, Rgba([0, 0, 0, 0]));
+            for y in 0..h {
+                if top + y >= canvas_h {
+                    continue;
+                }
+                for x in 0..w {
+                    if left + x >= canvas_w {
+                        continue;
+                    }
+                    region.put_pixel(x, y, *self.canvas.get_pixel(left + x, top + y));
+                }
+            }
+            self.saved_region = Some(region);
+        }
+
+        // Composite the frame's pixels onto the canvas; fully transparent
+        // pixels (alpha == 0, from the GIF's transparent color index) leave
+        // the existing canvas content showing through.
+        for y in 0..h {
+            if top + y >= canvas_h {
+                continue;
+            }
+            for x in 0..w {
+                if left + x >= canvas_w {
+                    continue;
+                }
+                let idx = ((y * w + x) * 4) as usize;
+                let pixel = &frame.buffer[idx..idx + 4];
+                if pixel[3] != 0 {
+                    self.canvas
+                        .put_pixel(left + x, top + y, Rgba([pixel[0], pixel[1], pixel[2], 255]));
+                }
+            }
+        }
+
+        self.pending_disposal = Some((frame.dispose, left, top, w, h));
+
+        // GIF frame delay is in hundredths of a second.
+        let delay_duration = Duration::from_millis(frame.delay as u64 * 10);
+        Some(Ok((self.canvas.clone(), delay_duration)))
+    }
+}
+
+// Render a single ASCII frame and save it as a still PNG.
+fn save_ascii_as_png(
+    ascii: &str,
+    glyph_width: u32,
+    glyph_height: u32,
+    output: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let canvas = render_ascii_frame(ascii, glyph_width, glyph_height)?;
+    canvas.save_with_format(output, ImageFormat::Png)?;
+    Ok(())
+}
+
+// Render every ASCII frame and encode them into an animated GIF, preserving
+// each frame's original delay and the resolved `repeat` count (the source
+// GIF's embedded NETSCAPE loop count, or infinite if `--loop_gif` forced it).
+fn save_ascii_frames_as_gif(
+    ascii_frames: &[(String, Duration)],
+    glyph_width: u32,
+    glyph_height: u32,
+    repeat: Repeat,
+    output: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file_out = File::create(output)?;
+    let mut encoder = GifEncoder::new(file_out);
+    encoder.set_repeat(repeat)?;
+
+    for (ascii, delay) in ascii_frames {
+        let canvas = render_ascii_frame(ascii, glyph_width, glyph_height)?;
+        let frame = Frame::from_parts(canvas, 0, 0, Delay::from_saturating_duration(*delay));
+        encoder.encode_frame(frame)?;
+    }
+
+    Ok(())
+}
+
+// --- Streaming, multi-threaded GIF pipeline ---
+//
+// Decodes and converts a GIF frame by frame: a decode thread pulls frames
+// lazily from `CompositedGifFrames` and hands them to a bounded channel, a
+// pool of worker threads converts frames to ASCII in parallel (order not
+// preserved), and a playback thread reorders the results back into original
+// frame order as they complete. When `play_live` is set, the playback
+// thread renders each frame to the terminal the moment it's back in order,
+// instead of waiting for every frame to finish converting first — this is
+// what lets animation start almost immediately. Frames are still buffered
+// into the returned `Vec` as they're played, since `--loop_gif` (or a
+// finite embedded repeat count) needs to replay them without re-decoding.
+//
+// Not used when `--denoise` is set: denoising needs each frame compared
+// against the running reference of the frame before it, which is
+// inherently sequential and incompatible with converting frames out of
+// order on independent worker threads.
+//
+// The decode-and-composite step (`CompositedGifFrames`) is itself
+// inherently sequential (each frame's canvas depends on the one before it),
+// so it still runs on a single decode thread; only the expensive
+// `image_to_ascii` conversion is parallelized across the worker pool.
+type GifStreamResult = Result<(Vec<(String, Duration)>, gif::Repeat), Box<dyn std::error::Error>>;
+
+fn convert_gif_streaming(
+    composited: CompositedGifFrames,
+    width: u32,
+    invert: bool,
+    contrast: f32,
+    use_color: bool,
+    color_mode: ColorMode,
+    play_live: bool,
+) -> GifStreamResult {
+    let num_workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let channel_bound = num_workers * 2;
+
+    // Decode thread: pulls composited frames lazily and feeds them to a
+    // bounded channel so decoding back-pressures on conversion instead of
+    // buffering the whole file. The GIF's NETSCAPE loop count is only known
+    // once every block (including the trailing application extension data)
+    // has been read, so the thread reports it back as its return value
+    // rather than reading `composited.repeat()` up front.
+    let (frame_tx, frame_rx) = mpsc::sync_channel::<(usize, DynamicImage, Duration)>(channel_bound);
+    let decode_handle = thread::spawn(move || {
+        let mut composited = composited;
+        let mut index = 0;
+        for frame_result in composited.by_ref() {
+            match frame_result {
+                Ok((rgba, delay_duration)) => {
+                    let frame_image = DynamicImage::ImageRgba8(rgba);
+                    if frame_tx.send((index, frame_image, delay_duration)).is_err() {
+                        break; // workers gone, stop decoding
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error decoding GIF frame {}: {}", index, e);
+                    break;
+                }
+            }
+            index += 1;
+        }
+        // `frame_tx` drops here, closing the channel for the worker pool.
+        composited.repeat()
+    });
+
+    // Worker pool: each worker pulls the next available frame and runs
+    // `image_to_ascii` on it; results may complete out of order. Conversion
+    // failures are forwarded as `Err` rather than dropped, so the playback
+    // thread below can still advance past the failed index instead of
+    // stalling forever waiting for it.
+    let frame_rx = Arc::new(Mutex::new(frame_rx));
+    let (result_tx, result_rx) = mpsc::channel::<(usize, Duration, Result<String, String>)>();
+
+    let worker_handles: Vec<_> = (0..num_workers)
+        .map(|_| {
+            let frame_rx = Arc::clone(&frame_rx);
+            let result_tx = result_tx.clone();
+            thread::spawn(move || loop {
+                let next = frame_rx.lock().unwrap().recv();
+                let Ok((index, frame_image, delay_duration)) = next else {
+                    break; // decode thread is done and channel is drained
+                };
+
+                let converted = image_to_ascii(&frame_image, width, invert, contrast, use_color, color_mode)
+                    .map_err(|e| e.to_string());
+                if result_tx.send((index, delay_duration, converted)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(result_tx); // only the workers' clones keep the channel open now
+
+    // Playback thread: reorders results back to original frame order as
+    // they arrive. When `play_live`, it renders each frame to the terminal
+    // as soon as it's in order, so playback starts well before the whole
+    // GIF has finished converting; otherwise it just reports progress. A
+    // conversion failure is forwarded as `Err` and ends playback rather than
+    // leaving later frames stuck pending forever behind the missing index.
+    let (ordered_tx, ordered_rx) = mpsc::channel::<Result<(String, Duration), String>>();
+    let playback_handle = thread::spawn(move || {
+        use std::io::Write;
+
+        let mut pending: BTreeMap<usize, (Duration, Result<String, String>)> = BTreeMap::new();
+        let mut next_index = 0;
+        for (index, delay, converted) in result_rx {
+            pending.insert(index, (delay, converted));
+            while let Some((delay, converted)) = pending.remove(&next_index) {
+                let ascii = match converted {
+                    Ok(ascii) => ascii,
+                    Err(message) => {
+                        let _ = ordered_tx.send(Err(message));
+                        return;
+                    }
+                };
+                if play_live {
+                    print!("\x1B[H"); // Move cursor to home before printing frame
+                    print!("{}", ascii);
+                    let _ = std::io::stdout().flush();
+                    let effective_delay = delay.max(Duration::from_millis(MIN_FRAME_DELAY_MS));
+                    thread::sleep(effective_delay);
+                } else {
+                    print!("\rConverted frame {}...", next_index + 1);
+                }
+                if ordered_tx.send(Ok((ascii, delay))).is_err() {
+                    return;
+                }
+                next_index += 1;
+            }
+        }
+    });
+
+    let mut ordered: Vec<(String, Duration)> = Vec::new();
+    let mut conversion_error: Option<String> = None;
+    for item in ordered_rx {
+        match item {
+            Ok(pair) => ordered.push(pair),
+            Err(message) => {
+                conversion_error = Some(message);
+                break;
+            }
+        }
+    }
+
+    let embedded_repeat = decode_handle.join().expect("GIF decode thread panicked");
+    for handle in worker_handles {
+        handle.join().expect("GIF conversion worker thread panicked");
+    }
+    playback_handle.join().expect("GIF playback thread panicked");
+
+    if let Some(message) = conversion_error {
+        return Err(format!("Error converting GIF frame: {}", message).into());
+    }
+
+    Ok((ordered, embedded_repeat))
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
@@ -151,70 +878,135 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             use std::io::Write; // Bring Write trait into scope for flush()
 
             println!("Detected GIF format. Processing frames...");
-            let file_in = File::open(&args.input)?;
-            let reader = BufReader::new(file_in);
-            let decoder = GifDecoder::new(reader)?;
-            let frames = decoder.into_frames();
-            let frames = frames.collect_frames()?; // Collect frames into memory
+            let gif_decoder = open_gif_decoder(&args.input)?;
+            let mut composited = CompositedGifFrames::new(gif_decoder);
 
-            if frames.is_empty() {
-                return Err("GIF contains no frames.".into());
+            // The NETSCAPE 2.0 loop count lives in an application extension
+            // that the `gif` crate only parses while reading frame blocks,
+            // not up front in `read_info` — so it isn't known until every
+            // frame (or at least the block containing it) has been read.
+            // Query it only after decoding below, never before.
+            //
+            // The streaming pipeline plays its first pass live as frames
+            // come back in order, so the terminal needs to be set up for
+            // animation before conversion even starts. That's only
+            // possible when we're not also denoising (which decodes
+            // eagerly) and not rendering to a file instead.
+            let play_live = args.output.is_none() && !args.denoise;
+            if play_live {
+                println!("Starting animation (Press Ctrl+C to stop)...");
+                print!("\x1B[2J\x1B[H");
+                std::io::stdout().flush()?;
+                thread::sleep(Duration::from_millis(50));
             }
-            println!("Processed {} frames.", frames.len());
-
-            // --- Convert Frames to ASCII ---
-            let mut ascii_frames: Vec<(String, Duration)> = Vec::with_capacity(frames.len());
-            for (i, frame) in frames.iter().enumerate() {
-                print!("\rConverting frame {}/{}...", i + 1, frames.len());
-                // Get delay - default to 100ms if missing (common default)
-                let delay = frame.delay().numer_denom_ms();
-                let delay_duration = Duration::from_millis(delay.0 as u64 / delay.1 as u64);
-                // Enforce minimum delay
-                // let effective_delay = delay_duration.max(Duration::from_millis(MIN_FRAME_DELAY_MS));
-
-                // Create DynamicImage from frame buffer
-                let buffer: &RgbaImage = frame.buffer();
-                let frame_image = DynamicImage::ImageRgba8(buffer.clone()); // Clone buffer
-
-                // Convert frame to ASCII
-                let ascii_frame = image_to_ascii(
-                    &frame_image,
+
+            let (ascii_frames, embedded_repeat): (Vec<(String, Duration)>, gif::Repeat) = if args.denoise
+            {
+                // Denoising needs every frame resized to identical dimensions
+                // and compared sequentially against a running reference, so
+                // it can't use the parallel streaming pipeline below: collect
+                // frames eagerly instead.
+                let mut prepared_frames: Vec<DynamicImage> = Vec::new();
+                let mut delays: Vec<Duration> = Vec::new();
+                for result in composited.by_ref() {
+                    let (rgba, delay_duration) = result?;
+                    delays.push(delay_duration);
+                    let frame_image = DynamicImage::ImageRgba8(rgba);
+                    prepared_frames.push(prepare_frame(&frame_image, args.width, args.color));
+                }
+                if prepared_frames.is_empty() {
+                    return Err("GIF contains no frames.".into());
+                }
+
+                println!("Denoising {} frames...", prepared_frames.len());
+                denoise_frames(&mut prepared_frames, args.color);
+
+                let mut ascii_frames = Vec::with_capacity(prepared_frames.len());
+                let total = prepared_frames.len();
+                for (i, (prepared, delay_duration)) in prepared_frames.iter().zip(delays).enumerate() {
+                    print!("\rConverting frame {}/{}...", i + 1, total);
+                    let ascii_frame = ascii_from_prepared(
+                        prepared,
+                        args.invert,
+                        args.contrast,
+                        args.color,
+                        args.color_mode,
+                    )?;
+                    ascii_frames.push((ascii_frame, delay_duration));
+                }
+                (ascii_frames, composited.repeat())
+            } else {
+                println!(
+                    "Streaming frames through a {}-worker pipeline...",
+                    thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+                );
+                convert_gif_streaming(
+                    composited,
                     args.width,
                     args.invert,
                     args.contrast,
                     args.color,
+                    args.color_mode,
+                    play_live,
+                )?
+            };
+
+            if ascii_frames.is_empty() {
+                return Err("GIF contains no frames.".into());
+            }
+            println!("\nProcessed {} frames.", ascii_frames.len());
+
+            let repeat = resolve_repeat(args.loop_gif, embedded_repeat);
+
+            // --- Render to file instead of animating in the terminal ---
+            if let Some(output) = &args.output {
+                println!("Rendering {} frames to {:?}...", ascii_frames.len(), output);
+                save_ascii_frames_as_gif(
+                    &ascii_frames,
+                    args.glyph_width,
+                    args.glyph_height,
+                    repeat,
+                    output,
                 )?;
-                // Store the *original* frame delay
-                ascii_frames.push((ascii_frame, delay_duration)); // Use original delay_duration
+                println!("Saved animated GIF to {:?}", output);
+                return Ok(());
             }
-            println!("\nFrame conversion complete.");
 
             // --- Animate in Terminal ---
-            println!("Starting animation (Press Ctrl+C to stop)...");
-            // Clear screen once before starting the loop
-            print!("\x1B[2J\x1B[H");
-            std::io::stdout().flush()?; // Ensure clear happens now
-            thread::sleep(Duration::from_millis(50)); // Small pause before starting
+            // When `play_live`, the streaming pipeline above already played the
+            // first pass live as frames came back in order, so the terminal is
+            // already set up and that pass shouldn't be rendered again here.
+            if !play_live {
+                println!("Starting animation (Press Ctrl+C to stop)...");
+                // Clear screen once before starting the loop
+                print!("\x1B[2J\x1B[H");
+                std::io::stdout().flush()?; // Ensure clear happens now
+                thread::sleep(Duration::from_millis(50)); // Small pause before starting
+            }
 
+            let mut plays_completed: u16 = 0;
             loop {
-                // Outer loop for optional GIF looping
-                for (ascii_frame, delay) in &ascii_frames {
-                    // Clear screen and move cursor to top-left (moved before loop)
-                    // print!("\x1B[2J\x1B[H");
-                    print!("\x1B[H"); // Move cursor to home before printing frame
-                                      // Print the frame
-                    print!("{}", ascii_frame);
-                    // Flush stdout to ensure it's displayed immediately
-                    // use std::io::Write; // Moved up
-                    std::io::stdout().flush()?;
-                    // Wait for the frame's effective delay (with minimum threshold)
-                    // thread::sleep(*delay); // Old version
-                    // Apply minimum delay threshold here
-                    let effective_delay = (*delay).max(Duration::from_millis(MIN_FRAME_DELAY_MS)); // Dereference delay
-                    thread::sleep(effective_delay); // Pass Duration value
-                }
-                if !args.loop_gif {
-                    break; // Exit loop if not looping indefinitely
+                // Outer loop for GIF looping, honoring the embedded NETSCAPE
+                // loop count (or --loop_gif forcing infinite looping). The
+                // very first pass is skipped here when it was already played
+                // live during streaming.
+                if plays_completed > 0 || !play_live {
+                    for (ascii_frame, delay) in &ascii_frames {
+                        print!("\x1B[H"); // Move cursor to home before printing frame
+                        print!("{}", ascii_frame);
+                        std::io::stdout().flush()?;
+                        // Apply minimum delay threshold here
+                        let effective_delay = (*delay).max(Duration::from_millis(MIN_FRAME_DELAY_MS));
+                        thread::sleep(effective_delay);
+                    }
+                }
+                plays_completed += 1;
+                let done = match repeat {
+                    Repeat::Infinite => false,
+                    Repeat::Finite(n) => plays_completed >= n,
+                };
+                if done {
+                    break;
                 }
                 // println!("\rLooping...        "); // Old version
                 print!("\rLooping...        "); // Use print! to avoid potential newline
@@ -232,10 +1024,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
 
             let ascii_art =
-                image_to_ascii(&img, args.width, args.invert, args.contrast, args.color)?;
+                image_to_ascii(&img, args.width, args.invert, args.contrast, args.color, args.color_mode)?;
 
-            println!("\n--- Generated ASCII Art ---");
-            println!("{}", ascii_art);
+            if let Some(output) = &args.output {
+                save_ascii_as_png(&ascii_art, args.glyph_width, args.glyph_height, output)?;
+                println!("Saved ASCII art image to {:?}", output);
+            } else {
+                println!("\n--- Generated ASCII Art ---");
+                println!("{}", ascii_art);
+            }
         }
     }
 